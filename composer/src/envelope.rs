@@ -0,0 +1,112 @@
+//! Self-describing proof envelopes for cross-language verifiers.
+//!
+//! The raw `merkle_proof` bytes and the wasm `FrameIndexProof` hex strings carry
+//! no structure a non-Rust verifier can introspect. [`ProofEnvelope`] packages
+//! everything a challenge produced — the album id, committed root, queried frame
+//! indices, VRF proof and compiled Merkle proof — into a single Preserves
+//! document. Preserves gives a canonical, deterministic binary encoding with a
+//! debuggable text form, so these commitments stay byte-for-byte reproducible
+//! across implementations.
+
+use crate::{ComposerError, ComposerResult};
+use preserves::value::{IOValue, NestedValue, Record, Value};
+
+/// Symbol label of the envelope record.
+const LABEL: &str = "morphism-proof";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofEnvelope {
+    pub id: [u8; 32],
+    pub root: [u8; 32],
+    pub frame_indices: Vec<u64>,
+    pub vrf_proof: Vec<u8>,
+    pub merkle_proof: Vec<u8>,
+}
+
+impl ProofEnvelope {
+    /// Build the Preserves document describing this envelope.
+    pub fn to_preserves(&self) -> IOValue {
+        let indices = self
+            .frame_indices
+            .iter()
+            .map(|i| IOValue::new(*i))
+            .collect::<Vec<_>>();
+        Value::Record(Record(vec![
+            IOValue::symbol(LABEL),
+            IOValue::new(&self.id[..]),
+            IOValue::new(&self.root[..]),
+            IOValue::new(indices),
+            IOValue::new(&self.vrf_proof[..]),
+            IOValue::new(&self.merkle_proof[..]),
+        ]))
+        .wrap()
+    }
+
+    /// Reconstruct an envelope from a Preserves document.
+    pub fn from_preserves(value: &IOValue) -> ComposerResult<Self> {
+        let record = value
+            .value()
+            .as_record(Some(5))
+            .ok_or_else(|| malformed("expected a 5-field record"))?;
+        if record.label().value().as_symbol().map(String::as_str) != Some(LABEL) {
+            return Err(malformed("unexpected record label"));
+        }
+        let fields = record.fields();
+        Ok(Self {
+            id: fixed_bytes(&fields[0])?,
+            root: fixed_bytes(&fields[1])?,
+            frame_indices: fields[2]
+                .value()
+                .as_sequence()
+                .ok_or_else(|| malformed("frame indices must be a sequence"))?
+                .iter()
+                .map(read_u64)
+                .collect::<ComposerResult<Vec<_>>>()?,
+            vrf_proof: bytes(&fields[3])?,
+            merkle_proof: bytes(&fields[4])?,
+        })
+    }
+
+    /// Canonical packed binary encoding.
+    pub fn encode(&self) -> ComposerResult<Vec<u8>> {
+        preserves::value::packed::to_vec(&self.to_preserves())
+            .map_err(|e| ComposerError::Preserves(e.to_string()))
+    }
+
+    /// Decode from the canonical packed binary encoding.
+    pub fn decode(bytes: &[u8]) -> ComposerResult<Self> {
+        let value = preserves::value::packed::from_bytes(bytes)
+            .map_err(|e| ComposerError::Preserves(e.to_string()))?;
+        Self::from_preserves(&value)
+    }
+
+    /// Human-readable Preserves text form, for debugging.
+    pub fn to_text(&self) -> String {
+        preserves::value::text::to_string(&self.to_preserves())
+    }
+}
+
+fn malformed(msg: &str) -> ComposerError {
+    ComposerError::Preserves(msg.to_string())
+}
+
+fn bytes(value: &IOValue) -> ComposerResult<Vec<u8>> {
+    value
+        .value()
+        .as_bytestring()
+        .cloned()
+        .ok_or_else(|| malformed("expected a byte string"))
+}
+
+fn fixed_bytes(value: &IOValue) -> ComposerResult<[u8; 32]> {
+    bytes(value)?
+        .try_into()
+        .map_err(|_| malformed("expected 32 bytes"))
+}
+
+fn read_u64(value: &IOValue) -> ComposerResult<u64> {
+    value
+        .value()
+        .as_u64()
+        .ok_or_else(|| malformed("expected an unsigned integer"))
+}
@@ -1,7 +1,10 @@
+pub mod envelope;
+pub mod serve;
 mod store;
 pub mod tac;
 
-pub use tac::Album;
+pub use envelope::ProofEnvelope;
+pub use tac::{Album, EpisodeMeta, HashMode, Scene, VerifyReport};
 
 use thiserror::Error;
 
@@ -27,4 +30,10 @@ pub enum ComposerError {
     AlbumNotFound,
     #[error("resource not found")]
     ResourceNotFound,
+    #[error("serve error: {0}")]
+    Serve(String),
+    #[error("preserves error: {0}")]
+    Preserves(String),
+    #[error("unsupported media: {0}")]
+    UnsupportedMedia(String),
 }
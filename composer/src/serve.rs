@@ -0,0 +1,151 @@
+//! Content-addressed storage-proof service.
+//!
+//! Episodes are addressed by the sha256 id they were appended under. A verifier
+//! evaluates its VRF off-line (the server holds no secret key) and POSTs the VRF
+//! output to `/challenge`; the server folds that output into frame indices the
+//! same way the wasm prover folds its own VRF output, then returns a compiled
+//! Merkle proof for exactly those leaves — proving retention of the challenged
+//! pieces without transferring the media. Because the indices are derived from
+//! the client's VRF output, the challenge is bound to the VRF the client
+//! actually evaluated rather than to an unrelated seed hash.
+//!
+//! The server folds the VRF output over the episode's real frame count, whereas
+//! the wasm prover's `FrameIndexProof.frame_indices` folds the same output over
+//! a fixed `MAX_FRAMES` ceiling. The two therefore diverge for episodes shorter
+//! than that ceiling: the indices returned here are the authoritative set the
+//! proof is compiled for, and the wasm field is advisory.
+
+use crate::{Album, ComposerError, ComposerResult};
+use std::io::Read;
+use tiny_http::{Method, Request, Response, Server};
+
+/// Number of frames a single challenge selects, mirroring the wasm prover.
+const CHALLENGE_FRAMES: usize = 3;
+
+pub struct ProofServer {
+    album: Album,
+}
+
+impl ProofServer {
+    pub fn new(album: Album) -> Self {
+        Self { album }
+    }
+
+    /// Block serving proof requests on `addr` (e.g. `"0.0.0.0:8080"`).
+    pub fn serve(&self, addr: &str) -> ComposerResult<()> {
+        let server = Server::http(addr).map_err(|e| ComposerError::Serve(e.to_string()))?;
+        log::info!("proof server listening on {addr}");
+        for request in server.incoming_requests() {
+            if let Err(e) = self.handle(request) {
+                log::error!("proof request failed: {e}");
+            }
+        }
+        Ok(())
+    }
+
+    fn handle(&self, mut request: Request) -> ComposerResult<()> {
+        let method = request.method().clone();
+        let url = request.url().to_string();
+        let mut segments = url.trim_start_matches('/').split('/');
+        let response = match (method, segments.next(), segments.next()) {
+            (Method::Head, Some("episode"), Some(id)) => self.exists(id),
+            (Method::Get, Some("episode"), Some(id)) => self.describe(id),
+            (Method::Post, Some("challenge"), Some(id)) => {
+                let mut vrf_output = String::new();
+                request.as_reader().read_to_string(&mut vrf_output)?;
+                self.challenge(id, vrf_output.trim())
+            }
+            _ => Ok(Response::from_string("not found").with_status_code(404)),
+        };
+        match response {
+            Ok(response) => request.respond(response).map_err(Into::into),
+            Err(e) => {
+                let code = match e {
+                    ComposerError::ResourceNotFound => 404,
+                    _ => 400,
+                };
+                request
+                    .respond(Response::from_string(e.to_string()).with_status_code(code))
+                    .map_err(Into::into)
+            }
+        }
+    }
+
+    fn exists(&self, id: &str) -> ComposerResult<Response<std::io::Empty>> {
+        let code = if self.album.root(parse_id(id)?)?.is_some() {
+            200
+        } else {
+            404
+        };
+        Ok(Response::empty(code))
+    }
+
+    fn describe(&self, id: &str) -> ComposerResult<Response<std::io::Cursor<Vec<u8>>>> {
+        let id = parse_id(id)?;
+        let root = self.album.root(id)?.ok_or(ComposerError::ResourceNotFound)?;
+        let body = format!(
+            "{{\"id\":\"0x{}\",\"root\":\"0x{}\"}}",
+            hex::encode(id),
+            hex::encode(root)
+        );
+        Ok(json(body))
+    }
+
+    fn challenge(
+        &self,
+        id: &str,
+        vrf_output: &str,
+    ) -> ComposerResult<Response<std::io::Cursor<Vec<u8>>>> {
+        let id = parse_id(id)?;
+        let frame_count = self.album.describe(id)?.frame_count;
+        if frame_count == 0 {
+            return Err(ComposerError::Serve("episode has no committed frames".to_string()));
+        }
+        let indices = select_frames(vrf_output, frame_count)?;
+        let proof = self.album.get_proof_of_frames(id, &indices)?;
+        let list = indices
+            .iter()
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let body = format!(
+            "{{\"frame_indices\":[{}],\"merkle_proof\":\"0x{}\"}}",
+            list,
+            hex::encode(&proof)
+        );
+        Ok(json(body))
+    }
+}
+
+/// Fold a client-supplied VRF output into the frame indices it challenges,
+/// using the same big-endian word fold as the wasm prover but over the
+/// episode's real frame count rather than the prover's hardcoded `MAX_FRAMES`
+/// ceiling. These indices — not the wasm `frame_indices` — are authoritative,
+/// since only they stay within the committed leaves. Malformed or too-short
+/// outputs are rejected rather than silently folded.
+fn select_frames(vrf_output: &str, frame_count: u64) -> ComposerResult<Vec<u64>> {
+    let bytes = hex::decode(vrf_output.trim_start_matches("0x"))
+        .map_err(|_| ComposerError::Serve("invalid VRF output hex".to_string()))?;
+    if bytes.len() < CHALLENGE_FRAMES * 4 {
+        return Err(ComposerError::Serve("VRF output too short".to_string()));
+    }
+    Ok((0..CHALLENGE_FRAMES)
+        .map(|i| {
+            let w = u32::from_be_bytes(bytes[i * 4..i * 4 + 4].try_into().expect("qed"));
+            w as u64 % frame_count
+        })
+        .collect())
+}
+
+fn parse_id(id: &str) -> ComposerResult<[u8; 32]> {
+    hex::decode(id.trim_start_matches("0x"))
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or(ComposerError::Serve("invalid episode id".to_string()))
+}
+
+fn json(body: String) -> Response<std::io::Cursor<Vec<u8>>> {
+    let header = tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+        .expect("qed");
+    Response::from_string(body).with_header(header)
+}
@@ -1,5 +1,5 @@
 use crate::ComposerResult;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use xmt::{
     error::Error,
     merge::MergeValue,
@@ -83,6 +83,39 @@ impl<V> SledStore<V> {
         Ok(())
     }
 
+    pub fn save_scenes<T: Serialize>(&mut self, scenes: &T) -> ComposerResult<()> {
+        self.db.insert(b"scenes", bincode::serialize(scenes)?)?;
+        Ok(())
+    }
+
+    pub fn read_scenes<T: DeserializeOwned>(&self) -> ComposerResult<Option<T>> {
+        self.db
+            .get(b"scenes")?
+            .map(|v| bincode::deserialize(&v).map_err(Into::into))
+            .transpose()
+    }
+
+    pub fn save_meta<T: Serialize>(&mut self, meta: &T) -> ComposerResult<()> {
+        self.db.insert(b"meta", bincode::serialize(meta)?)?;
+        Ok(())
+    }
+
+    pub fn read_meta<T: DeserializeOwned>(&self) -> ComposerResult<Option<T>> {
+        self.db
+            .get(b"meta")?
+            .map(|v| bincode::deserialize(&v).map_err(Into::into))
+            .transpose()
+    }
+
+    pub fn save_hash_mode(&mut self, mode: u8) -> ComposerResult<()> {
+        self.db.insert(b"hmode", &[mode])?;
+        Ok(())
+    }
+
+    pub fn read_hash_mode(&self) -> ComposerResult<Option<u8>> {
+        Ok(self.db.get(b"hmode")?.map(|v| v[0]))
+    }
+
     fn leaf_key(key: &H256) -> Result<Vec<u8>, Error> {
         let b = <[u8; 32]>::from(*key);
         bincode::serialize(&(b"leaf", b)).map_err(|e| Error::Store(e.to_string()))
@@ -1,19 +1,121 @@
 use crate::{store::SledStore, ComposerError, ComposerResult};
 use ffmpeg::{
-    codec::context::Context, format::input, media::Type, util::frame::video::Video as VideoFrame,
+    codec::context::Context,
+    format::{input, Pixel},
+    media::Type,
+    software::scaling::{context::Context as Scaler, flag::Flags},
+    util::frame::video::Video as VideoFrame,
 };
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
-use xmt::{blake2b::Blake2bHasher, SparseMerkleTree, H256};
+use xmt::{blake2b::Blake2bHasher, traits::StoreReadOps, SparseMerkleTree, H256};
 
 type MerkleTree = SparseMerkleTree<Blake2bHasher, H256, SledStore<H256>>;
 
+/// Default normalized inter-frame luma difference above which a scene cut is
+/// declared.
+const DEFAULT_SCENE_THRESHOLD: f64 = 0.3;
+/// Minimum number of frames a scene must span before a further cut is honoured,
+/// used to suppress flicker from brief luma spikes.
+const MIN_SCENE_LEN: u64 = 12;
+/// Maximum dHash Hamming distance tolerated when matching a re-decoded frame
+/// against its perceptually-committed leaf.
+const PERCEPTUAL_HAMMING_TOLERANCE: u32 = 5;
+/// Sentinel byte placed above the dHash region of a perceptual leaf so an
+/// all-zero hash never collides with the sparse tree's empty-leaf value.
+const PERCEPTUAL_LEAF_SENTINEL: u8 = 0x01;
+/// Upper bound on the number of frames an episode may commit, matching the
+/// prover's challenge index space.
+const MAX_FRAMES: u64 = 1200;
+
+/// Descriptor of an episode's source media, probed before the commitment and
+/// persisted alongside the root so callers can enumerate episodes without
+/// re-decoding.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpisodeMeta {
+    pub codec: String,
+    pub pixel_format: String,
+    pub width: u32,
+    pub height: u32,
+    pub frame_rate: f64,
+    pub frame_count: u64,
+}
+
+/// How a frame's bytes are turned into the `[u8; 32]` leaf it is committed
+/// under.
+///
+/// An episode picks its strategy at `append` time and records it next to the
+/// root so `verify` re-hashes the same way; exact and perceptual episodes can
+/// therefore coexist in one album.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum HashMode {
+    /// Byte-exact Blake2b of the raw decode buffer; any re-encode invalidates
+    /// every leaf.
+    #[default]
+    Exact,
+    /// 64-bit difference hash of a canonical grayscale thumbnail, tolerant of
+    /// transcodes and rescales.
+    Perceptual,
+}
+
+impl HashMode {
+    fn tag(self) -> u8 {
+        match self {
+            Self::Exact => 0,
+            Self::Perceptual => 1,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Self {
+        match tag {
+            1 => Self::Perceptual,
+            _ => Self::Exact,
+        }
+    }
+}
+
+/// A contiguous run of frames collapsed into a single committed unit.
+///
+/// Only the representative keyframe is hashed into the tree; the frame range is
+/// carried in the scene boundary table stored alongside the root.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scene {
+    pub index: u64,
+    pub start_frame: u64,
+    pub end_frame: u64,
+    /// `digest` of the representative keyframe's luma plane.
+    pub keyframe: [u8; 32],
+}
+
 #[derive(Debug, Clone)]
 pub struct Album {
     pub name: String,
     pub path: PathBuf,
+    hash_mode: HashMode,
     db: sled::Db,
 }
 
+/// Outcome of re-checking a media file against the tree it was committed under.
+///
+/// Rather than a single pass/fail the report pinpoints which frame indices
+/// failed, in the spirit of torrent-style piece verification.
+#[derive(Debug, Clone, Default)]
+pub struct VerifyReport {
+    /// Frames present in both the file and the tree whose hashes disagree.
+    pub corrupt: Vec<u64>,
+    /// Frames re-decoded from the file but absent from the stored tree.
+    pub missing: Vec<u64>,
+    /// Frames committed in the tree but no longer produced by the file.
+    pub extraneous: Vec<u64>,
+}
+
+impl VerifyReport {
+    /// `true` when every committed frame still matches the source file.
+    pub fn is_intact(&self) -> bool {
+        self.corrupt.is_empty() && self.missing.is_empty() && self.extraneous.is_empty()
+    }
+}
+
 impl Album {
     pub fn new(home: &Path, name: &str) -> Result<Self, ComposerError> {
         let path = home.join(sha256::digest(name));
@@ -25,6 +127,7 @@ impl Album {
         Ok(Self {
             name: name.to_string(),
             path,
+            hash_mode: HashMode::default(),
             db,
         })
     }
@@ -35,11 +138,18 @@ impl Album {
             .then(|| Self {
                 name: name.to_string(),
                 path: path.clone(),
+                hash_mode: HashMode::default(),
                 db: sled::open(&path).unwrap(),
             })
             .ok_or(ComposerError::AlbumNotFound)
     }
 
+    /// Select the frame hashing strategy used by subsequent `append` calls.
+    pub fn with_hash_mode(mut self, mode: HashMode) -> Self {
+        self.hash_mode = mode;
+        self
+    }
+
     pub fn find_episode(&self, id: [u8; 32]) -> Option<MerkleTree> {
         let store = SledStore::open(&self.db, id).ok()?;
         let root = store.read_root().ok()??;
@@ -48,6 +158,18 @@ impl Album {
 
     pub fn get_proof_of_frames(&self, id: [u8; 32], frames: &[u64]) -> ComposerResult<Vec<u8>> {
         let store = SledStore::open(&self.db, id)?;
+        // Bound against the frame count recorded in the episode's metadata; the
+        // check is skipped when no metadata record exists or its count is 0.
+        if let Some(meta) = store.read_meta::<EpisodeMeta>()? {
+            if meta.frame_count > 0 {
+                if let Some(&f) = frames.iter().find(|&&f| f >= meta.frame_count) {
+                    return Err(ComposerError::UnsupportedMedia(format!(
+                        "frame {f} out of bounds (episode has {} frames)",
+                        meta.frame_count
+                    )));
+                }
+            }
+        }
         let smt = MerkleTree::new(
             store.read_root()?.ok_or(ComposerError::ResourceNotFound)?,
             store,
@@ -65,21 +187,159 @@ impl Album {
         Ok(proof.0)
     }
 
+    pub fn verify<P>(&self, id: [u8; 32], media: &P) -> ComposerResult<VerifyReport>
+    where
+        P: AsRef<Path>,
+    {
+        ffmpeg::init()?;
+        let store = SledStore::open(&self.db, id)?;
+        store.read_root()?.ok_or(ComposerError::ResourceNotFound)?;
+        let mode = HashMode::from_tag(store.read_hash_mode()?.unwrap_or(0));
+        let frames = Self::dump_frames(media, |f| Self::hash_frame(mode, f))?;
+        let mut report = VerifyReport::default();
+        for (i, frame) in frames.iter().enumerate() {
+            let i = i as u64;
+            let key = H256::from(Self::digest(&i.to_be_bytes()));
+            match store.get_leaf(&key)? {
+                None => report.missing.push(i),
+                Some(stored) if !Self::leaves_match(mode, &<[u8; 32]>::from(stored), frame) => {
+                    report.corrupt.push(i)
+                }
+                Some(_) => {}
+            }
+        }
+        // Frames are committed at contiguous indices, so any leaf past the
+        // last re-decoded frame is a piece the current file no longer yields.
+        let mut i = frames.len() as u64;
+        while store
+            .get_leaf(&H256::from(Self::digest(&i.to_be_bytes())))?
+            .is_some()
+        {
+            report.extraneous.push(i);
+            i += 1;
+        }
+        log::debug!(
+            "verify {}: corrupt={:?} missing={:?} extraneous={:?}",
+            hex::encode(id),
+            report.corrupt,
+            report.missing,
+            report.extraneous
+        );
+        Ok(report)
+    }
+
     pub fn append<P>(&self, media: &P, overwrite: bool) -> Result<H256, ComposerError>
     where
         P: AsRef<Path>,
     {
         ffmpeg::init()?;
-        let id = sha256::try_digest(media.as_ref())
-            .map(|s| hex::decode(s).expect("qed"))
-            .map_err(|e| ComposerError::File(e))?
-            .try_into()
-            .expect("qed");
+        let id = Self::media_id(media)?;
+        let meta = Self::probe(media)?;
         let mut smt = self.new_empty_tree(id, overwrite)?;
-        let frames = Self::dump_frames(media, |f| Ok(Self::digest(f)))?;
+        let mode = self.hash_mode;
+        let frames = Self::dump_frames(media, |f| Self::hash_frame(mode, f))?;
+        Self::guard_frame_count(frames.len())?;
+        // Record the number of leaves actually committed: `probe` only sees the
+        // container-declared count, which is often 0 or disagrees with the
+        // decoder. Frame-bound checks must use this figure.
+        let meta = EpisodeMeta {
+            frame_count: frames.len() as u64,
+            ..meta
+        };
+        smt.store_mut().save_hash_mode(mode.tag())?;
+        smt.store_mut().save_meta(&meta)?;
         Self::save_frames(frames, &mut smt)
     }
 
+    pub fn append_scenes<P>(
+        &self,
+        media: &P,
+        threshold: Option<f64>,
+        overwrite: bool,
+    ) -> Result<H256, ComposerError>
+    where
+        P: AsRef<Path>,
+    {
+        ffmpeg::init()?;
+        let id = Self::media_id(media)?;
+        let meta = Self::probe(media)?;
+        let mut smt = self.new_empty_tree(id, overwrite)?;
+        let scenes = Self::dump_scenes(
+            media,
+            threshold.unwrap_or(DEFAULT_SCENE_THRESHOLD),
+            MIN_SCENE_LEN,
+        )?;
+        // Scenes commit exact keyframe digests; record the real decoded frame
+        // total (last scene's end) so `describe` reports an accurate count.
+        let frame_count = scenes.last().map(|s| s.end_frame + 1).unwrap_or_default();
+        let meta = EpisodeMeta { frame_count, ..meta };
+        smt.store_mut().save_hash_mode(HashMode::Exact.tag())?;
+        smt.store_mut().save_meta(&meta)?;
+        let v = scenes
+            .iter()
+            .map(|s| {
+                (
+                    H256::from(Self::digest(&s.index.to_be_bytes())),
+                    H256::from(s.keyframe),
+                )
+            })
+            .collect::<Vec<_>>();
+        let root = smt.update_all(v)?.clone();
+        smt.store_mut().save_root(&root)?;
+        smt.store_mut().save_scenes(&scenes)?;
+        log::debug!(
+            "{} scenes saved, root: 0x{}",
+            scenes.len(),
+            hex::encode(<[u8; 32]>::from(root.clone()))
+        );
+        Ok(root)
+    }
+
+    pub fn get_proof_of_scenes(&self, id: [u8; 32], scenes: &[u64]) -> ComposerResult<Vec<u8>> {
+        let store = SledStore::open(&self.db, id)?;
+        let smt = MerkleTree::new(
+            store.read_root()?.ok_or(ComposerError::ResourceNotFound)?,
+            store,
+        );
+        let keys = scenes
+            .iter()
+            .map(|s| H256::from(Self::digest(&s.to_be_bytes())))
+            .collect::<Vec<_>>();
+        let proof = smt.merkle_proof(keys.clone())?.compile(keys)?;
+        log::debug!(
+            "get proof of scenes {:?} => \n0x{}",
+            scenes,
+            hex::encode(&proof.0)
+        );
+        Ok(proof.0)
+    }
+
+    pub fn describe(&self, id: [u8; 32]) -> ComposerResult<EpisodeMeta> {
+        let store = SledStore::<H256>::open(&self.db, id)?;
+        store.read_meta()?.ok_or(ComposerError::ResourceNotFound)
+    }
+
+    pub fn root(&self, id: [u8; 32]) -> ComposerResult<Option<[u8; 32]>> {
+        let store = SledStore::<H256>::open(&self.db, id)?;
+        Ok(store.read_root()?.map(<[u8; 32]>::from))
+    }
+
+    pub fn scenes(&self, id: [u8; 32]) -> ComposerResult<Vec<Scene>> {
+        let store = SledStore::<H256>::open(&self.db, id)?;
+        store.read_scenes()?.ok_or(ComposerError::ResourceNotFound)
+    }
+
+    fn media_id<P>(media: &P) -> Result<[u8; 32], ComposerError>
+    where
+        P: AsRef<Path>,
+    {
+        sha256::try_digest(media.as_ref())
+            .map(|s| hex::decode(s).expect("qed"))
+            .map_err(ComposerError::File)?
+            .try_into()
+            .map_err(|_| ComposerError::File(std::io::ErrorKind::InvalidData.into()))
+    }
+
     fn new_empty_tree(&self, id: [u8; 32], force: bool) -> Result<MerkleTree, ComposerError> {
         let store = SledStore::open(&self.db, id)?;
         match store.read_root()? {
@@ -113,7 +373,7 @@ impl Album {
     fn dump_frames<F, R, P>(file: &P, f: F) -> Result<Vec<R>, ComposerError>
     where
         P: AsRef<std::path::Path>,
-        F: Fn(&[u8]) -> Result<R, ffmpeg::Error>,
+        F: Fn(&VideoFrame) -> Result<R, ffmpeg::Error>,
     {
         let mut frames = vec![];
         if let Ok(mut ictx) = input(file) {
@@ -134,7 +394,7 @@ impl Album {
                         decoder.send_packet(&packet)?;
                         let mut decoded = VideoFrame::empty();
                         while decoder.receive_frame(&mut decoded).is_ok() {
-                            let r = f(decoded.data(0))?;
+                            let r = f(&decoded)?;
                             frames.push(r);
                         }
                     }
@@ -148,6 +408,194 @@ impl Album {
         Ok(frames)
     }
 
+    fn dump_scenes<P>(file: &P, threshold: f64, min_len: u64) -> Result<Vec<Scene>, ComposerError>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let mut scenes: Vec<Scene> = vec![];
+        let mut prev_luma: Option<Vec<u8>> = None;
+        let mut frame_index: u64 = 0;
+        let mut scene_start: u64 = 0;
+        let mut keyframe = [0u8; 32];
+        if let Ok(mut ictx) = input(file) {
+            let input = ictx
+                .streams()
+                .best(Type::Video)
+                .ok_or(ffmpeg::Error::StreamNotFound)?;
+            let video_stream_index = input.index();
+            let context_decoder = Context::from_parameters(input.parameters())?;
+            if let Ok(mut decoder) = context_decoder.decoder().video() {
+                for (stream, packet) in ictx.packets() {
+                    if stream.index() == video_stream_index {
+                        decoder.send_packet(&packet)?;
+                        let mut decoded = VideoFrame::empty();
+                        while decoder.receive_frame(&mut decoded).is_ok() {
+                            let luma = decoded.data(0).to_vec();
+                            match &prev_luma {
+                                None => keyframe = Self::digest(&luma),
+                                Some(prev)
+                                    if Self::luma_diff(prev, &luma) > threshold
+                                        && frame_index - scene_start >= min_len =>
+                                {
+                                    scenes.push(Scene {
+                                        index: scenes.len() as u64,
+                                        start_frame: scene_start,
+                                        end_frame: frame_index - 1,
+                                        keyframe,
+                                    });
+                                    scene_start = frame_index;
+                                    keyframe = Self::digest(&luma);
+                                }
+                                Some(_) => {}
+                            }
+                            prev_luma = Some(luma);
+                            frame_index += 1;
+                        }
+                    }
+                }
+                decoder.send_eof()?;
+            }
+        }
+        if prev_luma.is_none() {
+            return Err(ComposerError::Media(ffmpeg::Error::StreamNotFound));
+        }
+        scenes.push(Scene {
+            index: scenes.len() as u64,
+            start_frame: scene_start,
+            end_frame: frame_index - 1,
+            keyframe,
+        });
+        Ok(scenes)
+    }
+
+    /// Mean absolute difference of two luma planes, normalized to `[0, 1]`.
+    fn luma_diff(a: &[u8], b: &[u8]) -> f64 {
+        let n = a.len().min(b.len());
+        if n == 0 {
+            return 0.0;
+        }
+        let sum: u64 = a
+            .iter()
+            .zip(b.iter())
+            .map(|(x, y)| (*x as i16 - *y as i16).unsigned_abs() as u64)
+            .sum();
+        (sum as f64 / n as f64) / 255.0
+    }
+
+    /// Probe the best video stream and validate its parameters before any frame
+    /// is committed, rejecting oversized or unsupported inputs.
+    fn probe<P>(media: &P) -> ComposerResult<EpisodeMeta>
+    where
+        P: AsRef<Path>,
+    {
+        let ictx = input(media)?;
+        let stream = ictx
+            .streams()
+            .best(Type::Video)
+            .ok_or(ffmpeg::Error::StreamNotFound)?;
+        let decoder = Context::from_parameters(stream.parameters())?
+            .decoder()
+            .video()?;
+        let rate = stream.avg_frame_rate();
+        let frame_rate = if rate.denominator() != 0 {
+            rate.numerator() as f64 / rate.denominator() as f64
+        } else {
+            0.0
+        };
+        let meta = EpisodeMeta {
+            codec: decoder
+                .codec()
+                .map(|c| c.name().to_string())
+                .unwrap_or_default(),
+            pixel_format: format!("{:?}", decoder.format()),
+            width: decoder.width(),
+            height: decoder.height(),
+            frame_rate,
+            frame_count: stream.frames().max(0) as u64,
+        };
+        if meta.width == 0 || meta.height == 0 {
+            return Err(ComposerError::UnsupportedMedia(format!(
+                "invalid resolution {}x{}",
+                meta.width, meta.height
+            )));
+        }
+        Self::guard_frame_count(meta.frame_count as usize)?;
+        Ok(meta)
+    }
+
+    fn guard_frame_count(count: usize) -> ComposerResult<()> {
+        (count as u64 <= MAX_FRAMES)
+            .then_some(())
+            .ok_or_else(|| {
+                ComposerError::UnsupportedMedia(format!(
+                    "{count} frames exceeds the limit of {MAX_FRAMES}"
+                ))
+            })
+    }
+
+    fn hash_frame(mode: HashMode, frame: &VideoFrame) -> Result<[u8; 32], ffmpeg::Error> {
+        match mode {
+            HashMode::Exact => Ok(Self::digest(frame.data(0))),
+            HashMode::Perceptual => Ok(Self::perceptual_leaf(Self::dhash(frame)?)),
+        }
+    }
+
+    /// 64-bit difference hash of a frame's canonical grayscale 9×8 thumbnail.
+    fn dhash(frame: &VideoFrame) -> Result<u64, ffmpeg::Error> {
+        let mut scaler = Scaler::get(
+            frame.format(),
+            frame.width(),
+            frame.height(),
+            Pixel::GRAY8,
+            9,
+            8,
+            Flags::BILINEAR,
+        )?;
+        let mut gray = VideoFrame::empty();
+        scaler.run(frame, &mut gray)?;
+        let data = gray.data(0);
+        let stride = gray.stride(0);
+        let mut hash = 0u64;
+        let mut bit = 0u64;
+        for r in 0..8usize {
+            let row = &data[r * stride..];
+            for c in 0..8usize {
+                if row[c] > row[c + 1] {
+                    hash |= 1 << bit;
+                }
+                bit += 1;
+            }
+        }
+        Ok(hash)
+    }
+
+    /// Left-pad a 64-bit perceptual hash into a leaf value.
+    ///
+    /// A fixed sentinel byte is set above the hash region so an all-zero dHash
+    /// (common for solid, faded or slate frames) never collapses to `H256::zero`
+    /// — the sparse tree's empty-leaf sentinel, which `update_all` would drop.
+    /// The hash bytes themselves are untouched, so [`leaves_match`] still folds
+    /// over the same 64 bits.
+    fn perceptual_leaf(hash: u64) -> [u8; 32] {
+        let mut leaf = [0u8; 32];
+        leaf[23] = PERCEPTUAL_LEAF_SENTINEL;
+        leaf[24..].copy_from_slice(&hash.to_be_bytes());
+        leaf
+    }
+
+    /// Whether a re-decoded leaf matches a committed one under `mode`. Perceptual
+    /// episodes accept any dHash within [`PERCEPTUAL_HAMMING_TOLERANCE`].
+    fn leaves_match(mode: HashMode, stored: &[u8; 32], recomputed: &[u8; 32]) -> bool {
+        match mode {
+            HashMode::Exact => stored == recomputed,
+            HashMode::Perceptual => {
+                let a = u64::from_be_bytes(stored[24..].try_into().expect("qed"));
+                let b = u64::from_be_bytes(recomputed[24..].try_into().expect("qed"));
+                (a ^ b).count_ones() <= PERCEPTUAL_HAMMING_TOLERANCE
+            }
+        }
+    }
+
     pub(crate) fn digest(c: &[u8]) -> [u8; 32] {
         use blake2::digest::{consts::U32, Digest};
         let mut hasher = blake2::Blake2b::<U32>::new();